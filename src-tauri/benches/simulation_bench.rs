@@ -0,0 +1,40 @@
+// Benchmarks the per-cell inner loop on a realistic dataset/grid size, to
+// track regressions in the incremental confusion-matrix counters and the
+// chunked parallel grid walk. Wired up via the `[[bench]]` entry in
+// Cargo.toml, with `criterion` under `[dev-dependencies]`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use aps_calculator_lib::simulation::{run_simulation, AgreementThresholds, SimulationConfig};
+
+fn realistic_config() -> SimulationConfig {
+    // 5000 data points, 68 imprecision steps x 141 bias steps.
+    let data: Vec<f64> = (0..5000).map(|i| 50.0 + (i % 200) as f64 * 0.5).collect();
+
+    SimulationConfig {
+        model: "Setting APS for imprecision and bias - Analytical rerun simulation".to_string(),
+        data,
+        cdls: vec![100.0, 200.0],
+        decimal_places: 1,
+        agreement_thresholds: AgreementThresholds { min: 90.0, des: 95.0, opt: 99.0 },
+        cv_i: None,
+        sample_size: None,
+        max_imprecision: Some(33.3),
+        max_bias: Some(35.0),
+        max_mu: None,
+        step_size_mu: None,
+        step_size_imp_bias: Some(0.5),
+        bootstrap_iters: None,
+        error_model: None,
+        num_replicates: Some(10),
+        master_seed: Some(42),
+    }
+}
+
+fn bench_run_simulation(c: &mut Criterion) {
+    c.bench_function("run_simulation_5000x_imp_bias_grid", |b| {
+        b.iter(|| run_simulation(black_box(realistic_config()), None::<&tauri::AppHandle<tauri::Wry>>))
+    });
+}
+
+criterion_group!(benches, bench_run_simulation);
+criterion_main!(benches);
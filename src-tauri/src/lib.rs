@@ -1,10 +1,10 @@
 
 
-mod data;
-mod simulation;
+pub mod data;
+pub mod simulation;
 
 use data::{read_excel_columns, read_csv_columns, get_column_data};
-use simulation::{run_simulation, SimulationConfig, SimulationResult};
+use simulation::{run_simulation, solve_aps, ApsSolution, SimulationConfig, SimulationResult};
 
 #[tauri::command]
 fn get_file_columns(path: String) -> Result<Vec<String>, String> {
@@ -35,15 +35,24 @@ fn load_column_data(path: String, column: String) -> Result<Vec<f64>, String> {
     get_column_data(&path, &column)
 }
 
+#[tauri::command]
+async fn solve_aps_async(config: SimulationConfig) -> Result<ApsSolution, String> {
+    // Offload to thread pool, same as run_simulation_async
+    tauri::async_runtime::spawn_blocking(move || solve_aps(config))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .invoke_handler(tauri::generate_handler![
-            get_file_columns, 
+            get_file_columns,
             run_simulation_async,
-            load_column_data
+            load_column_data,
+            solve_aps_async
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -4,7 +4,8 @@ use rand_distr::StandardNormal;
 use serde::{Serialize, Deserialize};
 use std::f64;
 use tauri::{Emitter, Window};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SimulationConfig {
@@ -20,6 +21,21 @@ pub struct SimulationConfig {
     pub max_mu: Option<f64>,
     pub step_size_mu: Option<f64>,
     pub step_size_imp_bias: Option<f64>,
+    /// Number of percentile-bootstrap resamples used to report confidence
+    /// intervals for agreement/sensitivity/specificity at each grid point.
+    /// Skipped entirely (CIs collapse to the point estimate) when `None`.
+    pub bootstrap_iters: Option<usize>,
+    /// `"additive"` (default, or `None`) applies noise as
+    /// `val * (1 + z * total_cv)`. `"multiplicative"` applies it log-normally
+    /// instead, which stays strictly positive at high CVs; see
+    /// `evaluate_cell`. Any other value panics — see `ErrorModel::from_config`.
+    pub error_model: Option<String>,
+    /// Number of noise replicates averaged per grid cell. Defaults to 10.
+    pub num_replicates: Option<usize>,
+    /// Seed all per-cell noise streams (and the subsampling shuffle) from
+    /// this value instead of the hard-coded defaults, so raising
+    /// `num_replicates` for a smoother heatmap stays reproducible.
+    pub master_seed: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -33,6 +49,11 @@ pub struct AgreementThresholds {
 pub struct SimulationResult {
     pub mu_data: Vec<SimulationPoint>,
     pub names: Vec<String>,
+    /// MU values where agreement/sensitivity/specificity cross each
+    /// `AgreementThresholds` level, linearly interpolated between the two
+    /// bracketing grid points instead of snapped to `step_size_mu`. Only
+    /// populated for the MU models; empty for imprecision/bias.
+    pub threshold_crossings: Vec<ThresholdCrossing>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -48,8 +69,48 @@ pub struct SimulationPoint {
     pub sublevel_agreement: Vec<f64>,
     pub sublevel_sensitivity: Vec<f64>,
     pub sublevel_specificity: Vec<f64>,
+    /// 95% percentile-bootstrap confidence interval, equal to `(point, point)`
+    /// when `SimulationConfig::bootstrap_iters` is `None`.
+    pub agreement_ci: (f64, f64),
+    pub sensitivity_ci: (f64, f64),
+    pub specificity_ci: (f64, f64),
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThresholdCrossing {
+    pub metric: String,
+    pub level: String,
+    pub value: f64,
+}
+
+/// Result of `solve_aps`: the limiting specification(s) that still meet each
+/// requested agreement category, instead of a full grid for the caller to scan.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApsSolution {
+    /// For the MU models: the largest allowable MU (as a fraction, e.g. 0.047
+    /// for 4.7%) that still meets each threshold level. `None` if even MU = 0
+    /// cannot meet that level.
+    pub mu_limits: Vec<ApsLimit>,
+    /// For the imprecision/bias models: the boundary contour of maximum
+    /// allowable imprecision as a function of bias, one contour per level.
+    pub imp_bias_contours: Vec<ImpBiasContour>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApsLimit {
+    pub level: String,
+    pub max_mu: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ImpBiasContour {
+    pub level: String,
+    /// (bias, max_allowable_imprecision) pairs, one per bias step at which
+    /// zero imprecision meets the level; other bias steps are omitted.
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Clone, Copy)]
 enum SimulationModel {
     MuAnalytical,
     MuResampling,
@@ -67,6 +128,10 @@ impl SimulationModel {
             _ => None,
         }
     }
+
+    fn is_mu_model(&self) -> bool {
+        matches!(self, Self::MuAnalytical | Self::MuResampling)
+    }
 }
 
 // Helper to categorize a value
@@ -85,9 +150,198 @@ fn round_to(value: f64, decimals: u32) -> f64 {
     (value * multiplier).round() / multiplier
 }
 
+/// Micro-average agreement/sensitivity/specificity from a flat list of
+/// (original_cat, predicted_cat) pairs, same definitions as the per-replicate
+/// metrics in `evaluate_cell`.
+fn macro_metrics(pairs: &[(usize, usize)], n_names: usize) -> (f64, f64, f64) {
+    // Same per-category counter trick as `evaluate_cell`: avoids building a
+    // full n_names×n_names matrix for what bootstrap_ci calls `iters` times
+    // per cell.
+    let total_samples = pairs.len();
+    let mut tp = vec![0usize; n_names];
+    let mut true_count = vec![0usize; n_names];
+    let mut pred_count = vec![0usize; n_names];
+    for &(true_cat, pred_cat) in pairs {
+        true_count[true_cat] += 1;
+        pred_count[pred_cat] += 1;
+        if true_cat == pred_cat {
+            tp[true_cat] += 1;
+        }
+    }
+
+    let tp_total: usize = tp.iter().sum();
+    let agreement = tp_total as f64 / total_samples as f64;
+    let sensitivity = agreement; // Micro Sens = Accuracy, as in evaluate_cell
+
+    let mut sum_tn = 0;
+    let mut sum_fp = 0;
+    for i in 0..n_names {
+        let fp = pred_count[i] - tp[i];
+        let tn = total_samples - true_count[i] - fp;
+        sum_tn += tn;
+        sum_fp += fp;
+    }
+    let specificity = if sum_tn + sum_fp > 0 { sum_tn as f64 / (sum_tn + sum_fp) as f64 } else { 0.0 };
 
-pub fn run_simulation<R: tauri::Runtime>(config: SimulationConfig, app_handle: Option<&tauri::AppHandle<R>>) -> SimulationResult {
-    // 1. Prepare Bins and Names
+    (agreement, sensitivity, specificity)
+}
+
+/// Percentile-bootstrap 95% CI for agreement/sensitivity/specificity,
+/// resampling a single replicate's N (original_cat, predicted_cat) pairs from
+/// a grid cell with replacement — not pooled across replicates, which would
+/// inflate the resample size to num_replicates*N and understate the true
+/// per-dataset uncertainty. Seeded deterministically from `(e, f)` so results
+/// stay reproducible across runs regardless of which thread evaluates the cell.
+fn bootstrap_ci(pairs: &[(usize, usize)], n_names: usize, iters: usize, e: f64, f: f64) -> ((f64, f64), (f64, f64), (f64, f64)) {
+    let mut rng = StdRng::seed_from_u64(cell_bootstrap_seed(e, f));
+    let n = pairs.len();
+
+    let mut agreement_samples = Vec::with_capacity(iters);
+    let mut sensitivity_samples = Vec::with_capacity(iters);
+    let mut specificity_samples = Vec::with_capacity(iters);
+
+    for _ in 0..iters {
+        let resampled: Vec<(usize, usize)> = (0..n).map(|_| pairs[rng.gen_range(0..n)]).collect();
+        let (agr, sens, spec) = macro_metrics(&resampled, n_names);
+        agreement_samples.push(agr);
+        sensitivity_samples.push(sens);
+        specificity_samples.push(spec);
+    }
+
+    (
+        percentile_ci(&mut agreement_samples),
+        percentile_ci(&mut sensitivity_samples),
+        percentile_ci(&mut specificity_samples),
+    )
+}
+
+fn percentile_ci(samples: &mut [f64]) -> (f64, f64) {
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    (percentile(samples, 2.5), percentile(samples, 97.5))
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    if lo == hi {
+        sorted[lo]
+    } else {
+        let frac = rank - lo as f64;
+        sorted[lo] * (1.0 - frac) + sorted[hi] * frac
+    }
+}
+
+fn cell_bootstrap_seed(e: f64, f: f64) -> u64 {
+    e.to_bits().wrapping_mul(0x9E3779B97F4A7C15).wrapping_add(f.to_bits())
+}
+
+/// Deterministic per-replicate noise seed hashing `(master_seed, e_idx, f_idx,
+/// replicate)` together, so every grid cell draws an independent stream
+/// instead of every cell reusing the same `num_replicates` noise vectors.
+/// Note this means noise is regenerated per cell rather than pre-generated
+/// once per replicate and hoisted out of the grid loops: a shared matrix would
+/// reintroduce the cross-cell correlation this per-cell seeding exists to
+/// remove, so the two approaches are mutually exclusive and decorrelation was
+/// kept.
+fn cell_noise_seed(master_seed: u64, e_idx: usize, f_idx: usize, replicate: usize) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    master_seed.hash(&mut hasher);
+    e_idx.hash(&mut hasher);
+    f_idx.hash(&mut hasher);
+    replicate.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// For each of agreement/sensitivity/specificity and each `AgreementThresholds`
+/// level, linearly interpolate the exact MU where the metric crosses that
+/// level, rather than snapping to the nearest `step_size_mu` grid point.
+/// `mu_data` must be sorted ascending by `mu` (true of `run_simulation`'s
+/// output for the MU models).
+fn find_threshold_crossings(mu_data: &[SimulationPoint], thresholds: &AgreementThresholds) -> Vec<ThresholdCrossing> {
+    let levels: [(&str, f64); 3] = [
+        ("min", thresholds.min),
+        ("des", thresholds.des),
+        ("opt", thresholds.opt),
+    ];
+    let metrics: [(&str, fn(&SimulationPoint) -> f64); 3] = [
+        ("agreement", |p| p.agreement * 100.0),
+        ("sensitivity", |p| p.sensitivity * 100.0),
+        ("specificity", |p| p.specificity * 100.0),
+    ];
+
+    let mut crossings = Vec::new();
+    for (metric_name, value_of) in metrics {
+        for (level_name, level) in levels {
+            if let Some(value) = interpolate_crossing(mu_data, value_of, level) {
+                crossings.push(ThresholdCrossing {
+                    metric: metric_name.to_string(),
+                    level: level_name.to_string(),
+                    value,
+                });
+            }
+        }
+    }
+    crossings
+}
+
+/// First point where `value_of` drops below `level`, linearly interpolated
+/// between the bracketing `(mu[i-1], metric[i-1])` and `(mu[i], metric[i])`.
+fn interpolate_crossing(mu_data: &[SimulationPoint], value_of: fn(&SimulationPoint) -> f64, level: f64) -> Option<f64> {
+    for i in 1..mu_data.len() {
+        let prev = value_of(&mu_data[i - 1]);
+        let curr = value_of(&mu_data[i]);
+        if prev >= level && curr < level {
+            if (curr - prev).abs() < f64::EPSILON {
+                return Some(mu_data[i].mu);
+            }
+            let t = (level - prev) / (curr - prev);
+            return Some(mu_data[i - 1].mu + t * (mu_data[i].mu - mu_data[i - 1].mu));
+        }
+    }
+    None
+}
+
+/// How noise is applied to a value: `"additive"` (default) or
+/// `"multiplicative"`; see `evaluate_cell`. Parsed once per run instead of
+/// matching `config.error_model`'s string on every sample, and rejects
+/// unrecognized values up front instead of silently falling back to additive.
+#[derive(Clone, Copy)]
+enum ErrorModel {
+    Additive,
+    Multiplicative,
+}
+
+impl ErrorModel {
+    fn from_config(config: &SimulationConfig) -> Self {
+        match config.error_model.as_deref() {
+            None | Some("additive") => Self::Additive,
+            Some("multiplicative") => Self::Multiplicative,
+            Some(other) => panic!(
+                "unrecognized error_model {:?}: expected \"additive\" or \"multiplicative\"",
+                other
+            ),
+        }
+    }
+}
+
+/// Everything derived from `SimulationConfig` that doesn't depend on the
+/// (e, f) grid point being evaluated: bins/names/model plus the (possibly
+/// subsampled) data and its original categorization.
+struct PreparedData {
+    bins: Vec<f64>,
+    names: Vec<String>,
+    data: Vec<f64>,
+    original_cats: Vec<usize>,
+    model: SimulationModel,
+    error_model: ErrorModel,
+}
+
+fn prepare_data(config: &SimulationConfig) -> PreparedData {
+    // Prepare Bins and Names
     let mut bins = vec![f64::NEG_INFINITY];
     // Python code uses: [0, cdl_1-epsilon, cdl_2, ..., inf]
     // But effectively it splits by CDLs.
@@ -108,35 +362,215 @@ pub fn run_simulation<R: tauri::Runtime>(config: SimulationConfig, app_handle: O
         names.push(format!("≥{}", config.cdls[config.cdls.len()-1]));
     }
 
-    // 2. Prepare Data (Subsampling if needed)
+    // Prepare Data (Subsampling if needed)
     let mut data = config.data.clone();
     if let Some(sample_size) = config.sample_size {
         if sample_size < data.len() {
-            let mut rng = StdRng::seed_from_u64(42);
+            let mut rng = StdRng::seed_from_u64(config.master_seed.unwrap_or(42));
             data.shuffle(&mut rng);
             data.truncate(sample_size);
         }
     }
 
-    // 3. Categorize Original Data
+    // Categorize Original Data
     let original_cats: Vec<usize> = data.iter()
         .map(|&val| categorize(val, &bins, &names))
         .collect();
 
-    // 4. Define Simulation Steps based on Model
     let model = SimulationModel::from_str(&config.model).unwrap_or(SimulationModel::MuAnalytical);
-    
-    let (e_steps, f_steps) = match model {
+    let error_model = ErrorModel::from_config(config);
+
+    PreparedData { bins, names, data, original_cats, model, error_model }
+}
+
+/// Evaluate a single (e, f) grid point, averaging the agreement/sensitivity/
+/// specificity metrics over `config.num_replicates` simulated replicates
+/// (10 by default). `e_idx`/`f_idx` are this cell's position in the grid,
+/// used only to seed its noise streams independently of every other cell.
+fn evaluate_cell(prepared: &PreparedData, config: &SimulationConfig, e: f64, f: f64, e_idx: usize, f_idx: usize) -> SimulationPoint {
+    let PreparedData { bins, names, data, original_cats, model, error_model } = prepared;
+    let model = *model;
+    let error_model = *error_model;
+    let num_replicates = config.num_replicates.unwrap_or(10);
+    let master_seed = config.master_seed.unwrap_or(42);
+
+    let mut total_agreement = 0.0;
+    let mut total_sensitivity = 0.0;
+    let mut total_specificity = 0.0;
+
+    let mut sub_agreement = vec![0.0; names.len()];
+    let mut sub_sensitivity = vec![0.0; names.len()];
+    let mut sub_specificity = vec![0.0; names.len()];
+
+    // (original_cat, predicted_cat) pairs for the bootstrap below, taken from
+    // a single replicate so resampling stays at the dataset's real resolution
+    // (N independent observations) instead of inflating it to
+    // num_replicates*N pseudo-observations that would understate the true
+    // uncertainty. Left empty (no allocation, no per-sample push) unless a
+    // bootstrap was actually requested.
+    let collect_bootstrap_pairs = config.bootstrap_iters.is_some();
+    let mut bootstrap_pairs: Vec<(usize, usize)> = if collect_bootstrap_pairs {
+        Vec::with_capacity(data.len())
+    } else {
+        Vec::new()
+    };
+
+    for replicate in 0..num_replicates {
+        let mut rng = StdRng::seed_from_u64(cell_noise_seed(master_seed, e_idx, f_idx, replicate));
+        let noise: Vec<f64> = (0..data.len())
+            .map(|_| rng.sample::<f64, _>(StandardNormal))
+            .collect();
+
+        // Calculate Total CV based on model
+        let total_cv = match model {
+            SimulationModel::MuResampling | SimulationModel::ImpBiasResampling => {
+                let cvi = config.cv_i.unwrap_or(0.0) / 100.0;
+                (e.powi(2) + cvi.powi(2)).sqrt()
+            },
+            _ => e
+        };
+
+        // Per-category counters accumulated in one pass over the samples,
+        // instead of a full names×names confusion matrix: `true_count[c]` and
+        // `pred_count[c]` are that category's row/column sums, so
+        // fn = true_count - tp and fp = pred_count - tp without ever
+        // building the O(names^2) matrix.
+        let mut tp = vec![0usize; names.len()];
+        let mut true_count = vec![0usize; names.len()];
+        let mut pred_count = vec![0usize; names.len()];
+
+        for (i, &val) in data.iter().enumerate() {
+            // y_od = result_t1 * (1 + imprec * total_cv), or for the
+            // multiplicative/log-normal model, val * exp(sigma*z - sigma^2/2)
+            // with sigma = total_cv (the `e` grid value for the analytical
+            // models, or the e/cv_i-combined total_cv for the resampling
+            // models) so E[y_od] = val in both models.
+            let y_od = match error_model {
+                ErrorModel::Multiplicative => {
+                    let sigma = total_cv;
+                    val * (sigma * noise[i] - sigma * sigma / 2.0).exp()
+                },
+                ErrorModel::Additive => val * (1.0 + noise[i] * total_cv),
+            };
+
+            // nd = y_od + val * f (Bias applied to original value in Python code: nd = y_od + result_t1*f)
+            let nd = y_od + val * f;
+
+            let nd_rounded = round_to(nd, config.decimal_places);
+            let pred_cat = categorize(nd_rounded, bins, names);
+            let true_cat = original_cats[i];
+
+            true_count[true_cat] += 1;
+            pred_count[pred_cat] += 1;
+            if true_cat == pred_cat {
+                tp[true_cat] += 1;
+            }
+            if collect_bootstrap_pairs && replicate == 0 {
+                bootstrap_pairs.push((true_cat, pred_cat));
+            }
+        }
+
+        // Calculate Metrics for this seed
+        let total_samples = data.len();
+        let tp_total: usize = tp.iter().sum();
+        total_agreement += tp_total as f64 / total_samples as f64;
+
+        // Micro-average Sensitivity/Specificity (as per Python code)
+        total_sensitivity += tp_total as f64 / total_samples as f64; // Micro Sens = Accuracy
+
+        // Specificity
+        // TN = Total - TP - FN - FP, per class, then summed (micro-average):
+        // Python: overall_specificity = np.sum(TN) / np.sum(TN + FP)
+        let mut sum_tn = 0;
+        let mut sum_fp = 0;
+
+        for i in 0..names.len() {
+            let fn_ = true_count[i] - tp[i];
+            let fp = pred_count[i] - tp[i];
+            let tn = total_samples - true_count[i] - fp;
+
+            sum_tn += tn;
+            sum_fp += fp;
+
+            // Sublevel metrics
+            // Accuracy (Subclass)
+            // accuracy = (TP+TN)/(TP+FP+FN+TN) = (TP+TN)/Total
+            sub_agreement[i] += (tp[i] + tn) as f64 / total_samples as f64;
+
+            // Sensitivity (Subclass)
+            // TP / (TP+FN)
+            sub_sensitivity[i] += if true_count[i] > 0 { tp[i] as f64 / true_count[i] as f64 } else { 0.0 };
+
+            // Specificity (Subclass)
+            // TN / (TN+FP)
+            sub_specificity[i] += if tn + fp > 0 { tn as f64 / (tn + fp) as f64 } else { 0.0 };
+        }
+
+        total_specificity += if sum_tn + sum_fp > 0 { sum_tn as f64 / (sum_tn + sum_fp) as f64 } else { 0.0 };
+    }
+
+    // Average over the replicates
+    let n = num_replicates as f64;
+    let avg_agreement = total_agreement / n;
+    let avg_sensitivity = total_sensitivity / n;
+    let avg_specificity = total_specificity / n;
+
+    let avg_sub_agreement: Vec<f64> = sub_agreement.iter().map(|x| x / n).collect();
+    let avg_sub_sensitivity: Vec<f64> = sub_sensitivity.iter().map(|x| x / n).collect();
+    let avg_sub_specificity: Vec<f64> = sub_specificity.iter().map(|x| x / n).collect();
+
+    let (agreement_ci, sensitivity_ci, specificity_ci) = match config.bootstrap_iters {
+        Some(iters) => bootstrap_ci(&bootstrap_pairs, names.len(), iters, e, f),
+        None => ((avg_agreement, avg_agreement), (avg_sensitivity, avg_sensitivity), (avg_specificity, avg_specificity)),
+    };
+
+    // Determine Categories
+    let get_cat = |val: f64| -> String {
+        let val_pct = val * 100.0;
+        if val_pct >= config.agreement_thresholds.opt {
+            format!("≥{}%", config.agreement_thresholds.opt)
+        } else if val_pct >= config.agreement_thresholds.des {
+            format!("≥{}%", config.agreement_thresholds.des)
+        } else if val_pct >= config.agreement_thresholds.min {
+            format!("≥{}%", config.agreement_thresholds.min)
+        } else {
+            format!("<{}%", config.agreement_thresholds.min)
+        }
+    };
+
+    SimulationPoint {
+        mu: e,
+        bias: f,
+        agreement: avg_agreement,
+        sensitivity: avg_sensitivity,
+        specificity: avg_specificity,
+        agreement_cat: get_cat(avg_agreement),
+        sensitivity_cat: get_cat(avg_sensitivity),
+        specificity_cat: get_cat(avg_specificity),
+        sublevel_agreement: avg_sub_agreement,
+        sublevel_sensitivity: avg_sub_sensitivity,
+        sublevel_specificity: avg_sub_specificity,
+        agreement_ci,
+        sensitivity_ci,
+        specificity_ci,
+    }
+}
+
+pub fn run_simulation<R: tauri::Runtime>(config: SimulationConfig, app_handle: Option<&tauri::AppHandle<R>>) -> SimulationResult {
+    let prepared = prepare_data(&config);
+
+    // Define Simulation Steps based on Model
+    let (e_steps, f_steps) = match prepared.model {
         SimulationModel::MuAnalytical | SimulationModel::MuResampling => {
             // MU Simulation
             // Default: 0 to 33.1% with 0.1% step
             let max_mu = config.max_mu.unwrap_or(33.1);
             let step_mu = config.step_size_mu.unwrap_or(0.1);
-            
+
             // Convert to fractions
             let step_frac = step_mu / 100.0;
             let num_steps = (max_mu / step_mu).round() as i32;
-            
+
             let e: Vec<f64> = (0..=num_steps).map(|i| i as f64 * step_frac).collect();
             let f = vec![0.0]; // Bias is 0 for MU simulation
             (e, f)
@@ -147,182 +581,173 @@ pub fn run_simulation<R: tauri::Runtime>(config: SimulationConfig, app_handle: O
             let max_imp = config.max_imprecision.unwrap_or(33.3);
             let max_bias = config.max_bias.unwrap_or(35.0);
             let step = config.step_size_imp_bias.unwrap_or(1.0);
-            
+
             let step_frac = step / 100.0;
-            
+
             let num_steps_imp = (max_imp / step).round() as i32;
             let num_steps_bias = (max_bias / step).round() as i32;
-            
+
             let e: Vec<f64> = (0..=num_steps_imp).map(|i| i as f64 * step_frac).collect();
             // Bias range: -max to +max
             let f: Vec<f64> = (-num_steps_bias..=num_steps_bias).map(|i| i as f64 * step_frac).collect();
-            
+
             (e, f)
         }
     };
 
     let total_steps = e_steps.len() * f_steps.len();
-    let progress_counter = Arc::new(Mutex::new(0));
-
-    // 5. Run Simulation (Parallel)
-    let mu_data: Vec<SimulationPoint> = e_steps.par_iter().flat_map(|&e| {
-        f_steps.par_iter().map(|&f| {
-            // Update progress
-            if let Some(handle) = app_handle {
-                let mut cnt = progress_counter.lock().unwrap();
-                *cnt += 1;
-                if *cnt % 50 == 0 || *cnt == total_steps { // Update less frequently to avoid flooding
-                    let _ = handle.emit("simulation-progress", *cnt as f64 / total_steps as f64 * 100.0);
+    // Atomic rather than a Mutex<i32>: every cell bumps this, and a lock
+    // contended on every one of tens of thousands of iterations dominated
+    // runtime more than the simulation work itself.
+    let progress_counter = Arc::new(AtomicUsize::new(0));
+
+    // f_steps is chunked so each rayon task does F_CHUNK_SIZE cells' worth of
+    // work instead of one, cutting task-scheduling overhead on large grids.
+    const F_CHUNK_SIZE: usize = 16;
+
+    // Run Simulation (Parallel)
+    let mu_data: Vec<SimulationPoint> = e_steps.par_iter().enumerate().flat_map(|(e_idx, &e)| {
+        f_steps.par_chunks(F_CHUNK_SIZE).enumerate().flat_map(|(chunk_idx, chunk)| {
+            chunk.iter().enumerate().map(move |(offset, &f)| {
+                let f_idx = chunk_idx * F_CHUNK_SIZE + offset;
+
+                // Update progress
+                let cnt = progress_counter.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(handle) = app_handle {
+                    if cnt % 50 == 0 || cnt == total_steps { // Update less frequently to avoid flooding
+                        let _ = handle.emit("simulation-progress", cnt as f64 / total_steps as f64 * 100.0);
+                    }
                 }
-            }
 
-            let mut total_agreement = 0.0;
-            let mut total_sensitivity = 0.0;
-            let mut total_specificity = 0.0;
-            
-            let mut sub_agreement = vec![0.0; names.len()];
-            let mut sub_sensitivity = vec![0.0; names.len()];
-            let mut sub_specificity = vec![0.0; names.len()];
-
-            // Run 10 seeds
-            for s in 1..=10 {
-                let mut rng = StdRng::seed_from_u64(s + 1234);
-                let noise: Vec<f64> = (0..data.len())
-                    .map(|_| rng.sample::<f64, _>(StandardNormal))
-                    .collect();
+                evaluate_cell(&prepared, &config, e, f, e_idx, f_idx)
+            }).collect::<Vec<_>>()
+        }).collect::<Vec<_>>()
+    }).collect();
 
-                let mut pred_cats = Vec::with_capacity(data.len());
-
-                // Calculate Total CV based on model
-                let total_cv = match model {
-                    SimulationModel::MuResampling | SimulationModel::ImpBiasResampling => {
-                        let cvi = config.cv_i.unwrap_or(0.0) / 100.0;
-                        (e.powi(2) + cvi.powi(2)).sqrt()
-                    },
-                    _ => e
-                };
-
-                for (i, &val) in data.iter().enumerate() {
-                    // y_od = result_t1 * (1 + imprec * total_cv)
-                    let y_od = val * (1.0 + noise[i] * total_cv);
-                    
-                    // nd = y_od + val * f (Bias applied to original value in Python code: nd = y_od + result_t1*f)
-                    let nd = y_od + val * f;
-
-                    let nd_rounded = round_to(nd, config.decimal_places);
-                    pred_cats.push(categorize(nd_rounded, &bins, &names));
-                }
+    let threshold_crossings = if prepared.model.is_mu_model() {
+        find_threshold_crossings(&mu_data, &config.agreement_thresholds)
+    } else {
+        Vec::new()
+    };
 
-                // Confusion Matrix
-                let mut cm = vec![vec![0; names.len()]; names.len()];
-                for (true_cat, pred_cat) in original_cats.iter().zip(pred_cats.iter()) {
-                    cm[*true_cat][*pred_cat] += 1;
-                }
+    SimulationResult {
+        mu_data,
+        names: prepared.names,
+        threshold_crossings,
+    }
+}
 
-                // Calculate Metrics for this seed
-                let total_samples = data.len();
-                let tp_total: usize = (0..names.len()).map(|i| cm[i][i]).sum();
-                total_agreement += tp_total as f64 / total_samples as f64;
-
-                // Micro-average Sensitivity/Specificity (as per Python code)
-                total_sensitivity += tp_total as f64 / total_samples as f64; // Micro Sens = Accuracy
-
-                // Specificity
-                // TN = Total - TP - FN - FP.
-                // For micro:
-                // TP_total = sum(diag).
-                // FN_total = sum(all) - TP_total.
-                // FP_total = sum(all) - TP_total.
-                // TN_total = sum(TN_i) ? No.
-                // Python: 
-                // TP = np.diag(cm)
-                // FN = sum(axis=1) - TP
-                // FP = sum(axis=0) - TP
-                // TN = sum(cm) - TP - FN - FP.
-                // This formula calculates TN for *each class* then sums them?
-                // Python: overall_specificity = np.sum(TN) / np.sum(TN + FP)
-                
-                let mut sum_tn = 0;
-                let mut sum_fp = 0;
-                
-                for i in 0..names.len() {
-                    let tp = cm[i][i];
-                    let fn_ = (0..names.len()).map(|j| cm[i][j]).sum::<usize>() - tp;
-                    let fp = (0..names.len()).map(|j| cm[j][i]).sum::<usize>() - tp;
-                    let tn = total_samples - tp - fn_ - fp;
-                    
-                    sum_tn += tn;
-                    sum_fp += fp;
-
-                    // Sublevel metrics
-                    // Accuracy (Subclass)
-                    // accuracy = (TP+TN)/(TP+FP+FN+TN) = (TP+TN)/Total
-                    sub_agreement[i] += (tp + tn) as f64 / total_samples as f64;
-
-                    // Sensitivity (Subclass)
-                    // TP / (TP+FN)
-                    sub_sensitivity[i] += if tp + fn_ > 0 { tp as f64 / (tp + fn_) as f64 } else { 0.0 };
-
-                    // Specificity (Subclass)
-                    // TN / (TN+FP)
-                    sub_specificity[i] += if tn + fp > 0 { tn as f64 / (tn + fp) as f64 } else { 0.0 };
-                }
-                
-                total_specificity += if sum_tn + sum_fp > 0 { sum_tn as f64 / (sum_tn + sum_fp) as f64 } else { 0.0 };
-            }
+/// Search for the limiting specification that still meets each requested
+/// agreement category, instead of leaving the caller to scan a full grid.
+///
+/// For the MU models, agreement is (approximately) monotonically
+/// non-increasing in MU, so each level is found by bisection on `[0, max_mu]`.
+/// For the imprecision/bias models, the boundary is traced by bisecting on
+/// imprecision independently at each bias step.
+pub fn solve_aps(config: SimulationConfig) -> ApsSolution {
+    let prepared = prepare_data(&config);
+    let levels = [
+        ("min", config.agreement_thresholds.min),
+        ("des", config.agreement_thresholds.des),
+        ("opt", config.agreement_thresholds.opt),
+    ];
+
+    match prepared.model {
+        SimulationModel::MuAnalytical | SimulationModel::MuResampling => {
+            let max_mu = config.max_mu.unwrap_or(33.1) / 100.0;
+            let step_mu = config.step_size_mu.unwrap_or(0.1) / 100.0;
 
-            // Average over 10 seeds
-            let avg_agreement = total_agreement / 10.0;
-            let avg_sensitivity = total_sensitivity / 10.0;
-            let avg_specificity = total_specificity / 10.0;
-            
-            let avg_sub_agreement: Vec<f64> = sub_agreement.iter().map(|x| x / 10.0).collect();
-            let avg_sub_sensitivity: Vec<f64> = sub_sensitivity.iter().map(|x| x / 10.0).collect();
-            let avg_sub_specificity: Vec<f64> = sub_specificity.iter().map(|x| x / 10.0).collect();
-
-            // Determine Categories
-            let get_cat = |val: f64| -> String {
-                let val_pct = val * 100.0;
-                if val_pct >= config.agreement_thresholds.opt {
-                    format!("≥{}%", config.agreement_thresholds.opt)
-                } else if val_pct >= config.agreement_thresholds.des {
-                    format!("≥{}%", config.agreement_thresholds.des)
-                } else if val_pct >= config.agreement_thresholds.min {
-                    format!("≥{}%", config.agreement_thresholds.min)
-                } else {
-                    format!("<{}%", config.agreement_thresholds.min)
+            let mu_limits = levels.iter().map(|&(level, target_pct)| {
+                ApsLimit {
+                    level: level.to_string(),
+                    max_mu: bisect_max_mu(&prepared, &config, 0.0, target_pct, max_mu, step_mu),
                 }
-            };
+            }).collect();
 
-            SimulationPoint {
-                mu: e,
-                bias: f,
-                agreement: avg_agreement,
-                sensitivity: avg_sensitivity,
-                specificity: avg_specificity,
-                agreement_cat: get_cat(avg_agreement),
-                sensitivity_cat: get_cat(avg_sensitivity),
-                specificity_cat: get_cat(avg_specificity),
-                sublevel_agreement: avg_sub_agreement,
-                sublevel_sensitivity: avg_sub_sensitivity,
-                sublevel_specificity: avg_sub_specificity,
-            }
-        }).collect::<Vec<_>>()
-    }).collect();
+            ApsSolution { mu_limits, imp_bias_contours: Vec::new() }
+        },
+        SimulationModel::ImpBiasAnalytical | SimulationModel::ImpBiasResampling => {
+            let max_imp = config.max_imprecision.unwrap_or(33.3) / 100.0;
+            let max_bias = config.max_bias.unwrap_or(35.0) / 100.0;
+            let step = config.step_size_imp_bias.unwrap_or(1.0) / 100.0;
 
-    SimulationResult {
-        mu_data,
-        names,
+            let num_steps_bias = (max_bias / step).round() as i32;
+            let bias_steps: Vec<f64> = (-num_steps_bias..=num_steps_bias).map(|i| i as f64 * step).collect();
+
+            let imp_bias_contours = levels.iter().map(|&(level, target_pct)| {
+                let points = bias_steps.iter()
+                    .filter_map(|&bias| {
+                        bisect_max_mu(&prepared, &config, bias, target_pct, max_imp, step)
+                            .map(|imp| (bias, imp))
+                    })
+                    .collect();
+                ImpBiasContour { level: level.to_string(), points }
+            }).collect();
+
+            ApsSolution { mu_limits: Vec::new(), imp_bias_contours }
+        }
     }
 }
 
+/// Bisect on the "e" axis (MU, or imprecision at a fixed bias `f`) for the
+/// largest value that still keeps agreement at or above `target_pct` (0-100).
+/// Returns `None` if even `e = 0` fails to meet the target.
+fn bisect_max_mu(prepared: &PreparedData, config: &SimulationConfig, f: f64, target_pct: f64, max_e: f64, step_e: f64) -> Option<f64> {
+    // Bisection queries aren't grid cells, so there's no natural (e_idx, f_idx)
+    // to seed from; a query counter still makes every query's noise stream
+    // independent and the search reproducible.
+    let mut query_id: usize = 0;
+    let mut agreement_at = |e: f64| {
+        query_id += 1;
+        evaluate_cell(prepared, config, e, f, 0, query_id).agreement * 100.0
+    };
+
+    if agreement_at(0.0) < target_pct {
+        return None;
+    }
+    if agreement_at(max_e) >= target_pct {
+        return Some(max_e);
+    }
+
+    // Coarse grid scan to bracket the first downward crossing, guarding
+    // against agreement being non-monotonic in practice.
+    const COARSE_STEPS: i32 = 20;
+    let mut lo = 0.0;
+    let mut hi = max_e;
+    for i in 1..=COARSE_STEPS {
+        let e = max_e * i as f64 / COARSE_STEPS as f64;
+        if agreement_at(e) >= target_pct {
+            lo = e;
+        } else {
+            hi = e;
+            break;
+        }
+    }
+
+    // Bisect inside the bracket.
+    for _ in 0..25 {
+        if hi - lo < step_e / 10.0 {
+            break;
+        }
+        let mid = (lo + hi) / 2.0;
+        if agreement_at(mid) >= target_pct {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    Some(lo)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_simulation_run() {
-        let config = SimulationConfig {
+    /// All-`None` config for the MU model, so each test only has to override
+    /// the fields it actually cares about.
+    fn base_config() -> SimulationConfig {
+        SimulationConfig {
             model: "Setting APS for measurement uncertainty - Analytical rerun simulation".to_string(),
             data: vec![100.0, 102.0, 98.0, 101.0, 99.0],
             cdls: vec![100.0],
@@ -330,12 +755,87 @@ mod tests {
             agreement_thresholds: AgreementThresholds { min: 90.0, des: 95.0, opt: 99.0 },
             cv_i: None,
             sample_size: None,
-        };
+            max_imprecision: None,
+            max_bias: None,
+            max_mu: None,
+            step_size_mu: None,
+            step_size_imp_bias: None,
+            bootstrap_iters: None,
+            error_model: None,
+            num_replicates: None,
+            master_seed: None,
+        }
+    }
 
-        let output = run_simulation(config, None);
+    #[test]
+    fn test_simulation_run() {
+        let config = base_config();
+
+        let output = run_simulation::<tauri::Wry>(config, None);
         assert!(!output.mu_data.is_empty());
-        
+
         let zero_point = output.mu_data.iter().find(|p| p.mu == 0.0 && p.bias.abs() < 1e-10).unwrap();
         assert_eq!(zero_point.agreement, 1.0);
     }
+
+    #[test]
+    fn test_solve_aps_mu_limits_are_monotone_in_strictness() {
+        // A stricter threshold (opt) can only tolerate MU at or below what a
+        // looser one (min) tolerates, since agreement is non-increasing in MU.
+        let config = SimulationConfig {
+            data: (0..200).map(|i| 100.0 + (i % 7) as f64 - 3.0).collect(),
+            max_mu: Some(20.0),
+            step_size_mu: Some(0.5),
+            ..base_config()
+        };
+
+        let solution = solve_aps(config);
+        let limit = |level: &str| {
+            solution.mu_limits.iter().find(|l| l.level == level).unwrap().max_mu
+        };
+        let (min, des, opt) = (limit("min"), limit("des"), limit("opt"));
+
+        if let (Some(min), Some(des)) = (min, des) {
+            assert!(min >= des, "min limit {min} should be >= des limit {des}");
+        }
+        if let (Some(des), Some(opt)) = (des, opt) {
+            assert!(des >= opt, "des limit {des} should be >= opt limit {opt}");
+        }
+    }
+
+    #[test]
+    fn test_multiplicative_error_model_stays_close_to_additive_for_small_cv() {
+        // For small total_cv, exp(sigma*z - sigma^2/2) ≈ 1 + sigma*z, so both
+        // models should agree closely on the resulting grid point. Both
+        // configs share the same master_seed/num_replicates, so this is a
+        // deterministic comparison, not a statistical one.
+        let shared = SimulationConfig {
+            data: (0..500).map(|i| 100.0 + (i % 5) as f64 - 2.0).collect(),
+            max_mu: Some(2.0),
+            step_size_mu: Some(2.0),
+            num_replicates: Some(50),
+            master_seed: Some(7),
+            ..base_config()
+        };
+
+        let additive = run_simulation::<tauri::Wry>(
+            SimulationConfig { error_model: Some("additive".to_string()), ..shared.clone() },
+            None,
+        );
+        let multiplicative = run_simulation::<tauri::Wry>(
+            SimulationConfig { error_model: Some("multiplicative".to_string()), ..shared },
+            None,
+        );
+
+        let agreement_at = |result: &SimulationResult, mu: f64| {
+            result.mu_data.iter().find(|p| (p.mu - mu).abs() < 1e-9).unwrap().agreement
+        };
+
+        let additive_agreement = agreement_at(&additive, 0.02);
+        let multiplicative_agreement = agreement_at(&multiplicative, 0.02);
+        assert!(
+            (additive_agreement - multiplicative_agreement).abs() < 0.05,
+            "additive={additive_agreement}, multiplicative={multiplicative_agreement}"
+        );
+    }
 }